@@ -0,0 +1,483 @@
+//! Pluggable backends for discovering and streaming `.dot` dumps.
+//!
+//! `GstDots` used to be wired directly to a single local directory: a
+//! `notify` watcher fed clients straight from the filesystem. The
+//! [`DotSource`] trait pulls that dependency out so a dump can originate
+//! anywhere - today a directory on disk ([`LocalFsSource`]), a socket a
+//! GStreamer app pushes dumps into directly ([`SocketPushSource`]), or
+//! another gst-dots instance's own WebSocket feed ([`RemoteSource`]) - while
+//! `GstDots` only ever deals in [`DotEvent`]s.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{event, Level};
+
+/// A change reported by a [`DotSource`].
+#[derive(Debug, Clone)]
+pub enum DotEvent {
+    Added {
+        name: String,
+        content: String,
+        mtime: u128,
+    },
+    Removed {
+        name: String,
+    },
+}
+
+/// Callback a [`DotSource`] invokes for every [`DotEvent`] it produces.
+pub type DotEventSink = Arc<dyn Fn(DotEvent) + Send + Sync>;
+
+/// Something that can enumerate `.dot` dumps and notify of future changes.
+pub trait DotSource: Send + Sync + std::fmt::Debug {
+    /// Every dot currently known to this source, as `(name, content, mtime)`.
+    fn list(&self) -> Vec<(String, String, u128)>;
+
+    /// Start delivering future [`DotEvent`]s to `sink`. Sources that watch
+    /// something asynchronous (a filesystem, a socket) spawn their own
+    /// background task here; this must not block.
+    fn subscribe(&self, sink: DotEventSink);
+}
+
+fn system_time_to_millis(t: SystemTime) -> u128 {
+    t.duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+/// The original behavior: a directory of `.dot` files watched with `notify`.
+pub struct LocalFsSource {
+    root: PathBuf,
+    watcher: Mutex<Option<notify::RecommendedWatcher>>,
+}
+
+impl std::fmt::Debug for LocalFsSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalFsSource").field("root", &self.root).finish()
+    }
+}
+
+impl LocalFsSource {
+    pub fn new(root: PathBuf) -> Arc<Self> {
+        Arc::new(Self {
+            root,
+            watcher: Mutex::new(None),
+        })
+    }
+
+    fn relative_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn collect(path: &Path, entries: &mut Vec<(PathBuf, SystemTime)>) {
+        if let Ok(read_dir) = std::fs::read_dir(path) {
+            for entry in read_dir.flatten() {
+                let dot_path = entry.path();
+                if dot_path.is_dir() {
+                    Self::collect(&dot_path, entries);
+                } else if dot_path.extension().and_then(|e| e.to_str()) == Some("dot") {
+                    if let Ok(metadata) = dot_path.metadata() {
+                        if let Ok(modified) = metadata.modified() {
+                            entries.push((dot_path, modified));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl DotSource for LocalFsSource {
+    fn list(&self) -> Vec<(String, String, u128)> {
+        let mut entries = Vec::new();
+        Self::collect(&self.root, &mut entries);
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+
+        entries
+            .into_iter()
+            .filter_map(|(path, mtime)| {
+                let content = match std::fs::read_to_string(&path) {
+                    Ok(c) if !c.is_empty() => c,
+                    Ok(_) => {
+                        event!(Level::ERROR, "===>Empty file: {:?}", path);
+                        return None;
+                    }
+                    Err(_) => {
+                        event!(Level::ERROR, "===>Error reading file: {:?}", path);
+                        return None;
+                    }
+                };
+                Some((
+                    self.relative_path(&path),
+                    content,
+                    system_time_to_millis(mtime),
+                ))
+            })
+            .collect()
+    }
+
+    fn subscribe(&self, sink: DotEventSink) {
+        let root = self.root.clone();
+        let relative = {
+            let root = root.clone();
+            move |path: &Path| -> String {
+                path.strip_prefix(&root)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .to_string()
+            }
+        };
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: Result<notify::Event, notify::Error>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => {
+                        event!(Level::ERROR, "watch error: {:?}", err);
+                        return;
+                    }
+                };
+
+                if !event
+                    .paths
+                    .iter()
+                    .any(|p| p.extension().map(|e| e == "dot").unwrap_or(false))
+                {
+                    return;
+                }
+
+                match event.kind {
+                    notify::event::EventKind::Modify(notify::event::ModifyKind::Data(
+                        notify::event::DataChange::Content,
+                    )) => {
+                        for path in event.paths.iter() {
+                            if path.extension().map(|e| e == "dot").unwrap_or(false) {
+                                let mtime = path
+                                    .metadata()
+                                    .and_then(|m| m.modified())
+                                    .map(system_time_to_millis)
+                                    .unwrap_or_default();
+                                match std::fs::read_to_string(path) {
+                                    Ok(content) => sink(DotEvent::Added {
+                                        name: relative(path),
+                                        content,
+                                        mtime,
+                                    }),
+                                    Err(err) => {
+                                        event!(Level::ERROR, "Could not read file {path:?}: {err:?}")
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    notify::event::EventKind::Remove(_) => {
+                        for path in event.paths.iter() {
+                            if path.extension().map(|e| e == "dot").unwrap_or(false) {
+                                sink(DotEvent::Removed {
+                                    name: relative(path),
+                                });
+                            }
+                        }
+                    }
+                    _ => (),
+                }
+            })
+            .expect("Could not create dot_watcher");
+
+        event!(Level::INFO, "Watching dot files in {:?}", root);
+        watcher
+            .watch(root.as_path(), notify::RecursiveMode::Recursive)
+            .unwrap();
+        *self.watcher.lock().unwrap() = Some(watcher);
+    }
+}
+
+/// Parse one pushed frame's JSON body into `(name, content)`, rejecting
+/// anything that isn't a `{"name": ..., "content": ...}` object with string
+/// fields. Pulled out of [`SocketPushSource::accept_loop`] so it's testable
+/// without a socket.
+fn parse_push_frame(bytes: &[u8]) -> Result<(String, String), String> {
+    let frame: serde_json::Value = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+    let (Some(name), Some(content)) = (
+        frame.get("name").and_then(|v| v.as_str()),
+        frame.get("content").and_then(|v| v.as_str()),
+    ) else {
+        return Err("missing name/content".to_string());
+    };
+    Ok((name.to_string(), content.to_string()))
+}
+
+/// A source that accepts dumps pushed over a TCP socket instead of reading
+/// them from disk, for headless/containerized setups where writing dot
+/// files to `GST_DEBUG_DUMP_DOT_DIR` is inconvenient.
+///
+/// Frames are length-prefixed JSON: a `u32` big-endian byte count followed
+/// by a `{"name": ..., "content": ...}` object.
+pub struct SocketPushSource {
+    address: String,
+    dots: Arc<Mutex<std::collections::HashMap<String, (String, u128)>>>,
+}
+
+impl std::fmt::Debug for SocketPushSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SocketPushSource")
+            .field("address", &self.address)
+            .finish()
+    }
+}
+
+impl SocketPushSource {
+    pub fn new(address: String) -> Arc<Self> {
+        Arc::new(Self {
+            address,
+            dots: Arc::new(Mutex::new(std::collections::HashMap::new())),
+        })
+    }
+
+    async fn accept_loop(address: String, dots: Arc<Mutex<std::collections::HashMap<String, (String, u128)>>>, sink: DotEventSink) {
+        let listener = match TcpListener::bind(&address).await {
+            Ok(l) => l,
+            Err(err) => {
+                event!(Level::ERROR, "Could not bind dot push socket {address}: {err:?}");
+                return;
+            }
+        };
+        event!(Level::INFO, "Accepting pushed dots on {address}");
+
+        loop {
+            let (socket, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    event!(Level::ERROR, "push socket accept error: {err:?}");
+                    continue;
+                }
+            };
+            event!(Level::INFO, "Dot push client connected: {peer:?}");
+
+            let dots = dots.clone();
+            let sink = sink.clone();
+            tokio::spawn(async move {
+                let mut socket = socket;
+                loop {
+                    let mut len_buf = [0u8; 4];
+                    if socket.read_exact(&mut len_buf).await.is_err() {
+                        event!(Level::INFO, "Dot push client disconnected: {peer:?}");
+                        return;
+                    }
+                    let len = u32::from_be_bytes(len_buf) as usize;
+                    let mut buf = vec![0u8; len];
+                    if socket.read_exact(&mut buf).await.is_err() {
+                        event!(Level::ERROR, "Dot push client {peer:?} dropped mid-frame");
+                        return;
+                    }
+
+                    let (name, content) = match parse_push_frame(&buf) {
+                        Ok(parsed) => parsed,
+                        Err(err) => {
+                            event!(Level::ERROR, "Invalid dot push frame from {peer:?}: {err}");
+                            continue;
+                        }
+                    };
+
+                    let mtime = system_time_to_millis(SystemTime::now());
+                    dots.lock()
+                        .unwrap()
+                        .insert(name.clone(), (content.clone(), mtime));
+
+                    sink(DotEvent::Added { name, content, mtime });
+
+                    let _ = socket.write_u8(1).await;
+                }
+            });
+        }
+    }
+}
+
+impl DotSource for SocketPushSource {
+    fn list(&self) -> Vec<(String, String, u128)> {
+        self.dots
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, (content, mtime))| (name.clone(), content.clone(), *mtime))
+            .collect()
+    }
+
+    fn subscribe(&self, sink: DotEventSink) {
+        let address = self.address.clone();
+        let dots = self.dots.clone();
+        crate::RUNTIME.spawn(Self::accept_loop(address, dots, sink));
+    }
+}
+
+/// A source that aggregates another gst-dots instance's own WebSocket feed,
+/// namespacing every name with the upstream host so one viewer can watch a
+/// whole fleet of remote gst-dots processes (CI runners, embedded boards...)
+/// at once.
+pub struct RemoteSource {
+    host: String,
+    dots: Arc<Mutex<HashMap<String, (String, u128)>>>,
+}
+
+impl RemoteSource {
+    pub fn new(host: String) -> Arc<Self> {
+        Arc::new(Self {
+            host,
+            dots: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Keep (re)connecting to the upstream for as long as the process runs.
+    /// A disconnect drops every dot we'd attributed to that host.
+    async fn connect_loop(
+        host: String,
+        dots: Arc<Mutex<HashMap<String, (String, u128)>>>,
+        sink: DotEventSink,
+    ) {
+        loop {
+            if let Err(err) = Self::connect_once(&host, &dots, &sink).await {
+                event!(Level::ERROR, "Lost connection to upstream {host}: {err}");
+            }
+
+            let stale: Vec<String> = dots.lock().unwrap().keys().cloned().collect();
+            for name in stale {
+                dots.lock().unwrap().remove(&name);
+                sink(DotEvent::Removed { name });
+            }
+
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+    }
+
+    async fn connect_once(
+        host: &str,
+        dots: &Arc<Mutex<HashMap<String, (String, u128)>>>,
+        sink: &DotEventSink,
+    ) -> Result<(), String> {
+        let url = format!("ws://{host}/ws/");
+        event!(Level::INFO, "Connecting to upstream gst-dots at {url}");
+        let (_, mut ws) = awc::Client::new()
+            .ws(&url)
+            .connect()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        ws.send(awc::ws::Message::Text(
+            serde_json::json!({ "type": "Manifest", "known": {} })
+                .to_string()
+                .into(),
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+
+        while let Some(frame) = ws.next().await {
+            let frame = frame.map_err(|e| e.to_string())?;
+            let awc::ws::Frame::Text(bytes) = frame else {
+                continue;
+            };
+
+            let message: serde_json::Value =
+                serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+            match message.get("type").and_then(|t| t.as_str()) {
+                Some("NewDot") => {
+                    let Some(name) = message.get("name").and_then(|n| n.as_str()) else {
+                        continue;
+                    };
+                    let content = message
+                        .get("content")
+                        .and_then(|c| c.as_str())
+                        .unwrap_or_default()
+                        .to_string();
+                    let mtime = message
+                        .get("creation_time")
+                        .and_then(|m| m.as_u64())
+                        .unwrap_or_default() as u128;
+
+                    let namespaced = format!("{host}/{name}");
+                    dots.lock()
+                        .unwrap()
+                        .insert(namespaced.clone(), (content.clone(), mtime));
+                    sink(DotEvent::Added {
+                        name: namespaced,
+                        content,
+                        mtime,
+                    });
+                }
+                Some("DotRemoved") => {
+                    let Some(name) = message.get("name").and_then(|n| n.as_str()) else {
+                        continue;
+                    };
+                    let namespaced = format!("{host}/{name}");
+                    dots.lock().unwrap().remove(&namespaced);
+                    sink(DotEvent::Removed { name: namespaced });
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for RemoteSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteSource").field("host", &self.host).finish()
+    }
+}
+
+impl DotSource for RemoteSource {
+    fn list(&self) -> Vec<(String, String, u128)> {
+        self.dots
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, (content, mtime))| (name.clone(), content.clone(), *mtime))
+            .collect()
+    }
+
+    fn subscribe(&self, sink: DotEventSink) {
+        let host = self.host.clone();
+        let dots = self.dots.clone();
+        crate::RUNTIME.spawn(Self::connect_loop(host, dots, sink));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_well_formed_frame() {
+        let frame = serde_json::json!({ "name": "a.dot", "content": "digraph {}" });
+        assert_eq!(
+            parse_push_frame(frame.to_string().as_bytes()),
+            Ok(("a.dot".to_string(), "digraph {}".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        assert!(parse_push_frame(b"not json").is_err());
+    }
+
+    #[test]
+    fn rejects_missing_fields() {
+        let frame = serde_json::json!({ "name": "a.dot" });
+        assert!(parse_push_frame(frame.to_string().as_bytes()).is_err());
+    }
+
+    #[test]
+    fn rejects_non_string_fields() {
+        let frame = serde_json::json!({ "name": "a.dot", "content": 42 });
+        assert!(parse_push_frame(frame.to_string().as_bytes()).is_err());
+    }
+}
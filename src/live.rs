@@ -0,0 +1,196 @@
+//! Optional live-debugger mode.
+//!
+//! Everything else in this crate only ever shows a `.dot` dump that already
+//! happened. `--live <launch description>` instead has the server build and
+//! run that pipeline itself, then periodically walk its element tree and
+//! stream topology + per-element stats diffs over the same WebSocket used
+//! for static dumps, so the viewer can animate state changes live.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use gst::prelude::*;
+use serde_json::{json, Value};
+use tracing::{event, Level};
+
+use crate::GstDots;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Convert a `glib::Value` to JSON, recursing into `gst::Structure`s.
+/// Returns `None` for value types we don't know how to represent - callers
+/// should just omit the field rather than fail the whole update.
+pub fn serialize_value(value: &glib::Value) -> Option<Value> {
+    match value.type_() {
+        glib::Type::STRING => value.get::<Option<String>>().ok().flatten().map(Value::from),
+        glib::Type::BOOL => value.get::<bool>().ok().map(Value::from),
+        glib::Type::I32 => value.get::<i32>().ok().map(Value::from),
+        glib::Type::U32 => value.get::<u32>().ok().map(Value::from),
+        glib::Type::I64 => value.get::<i64>().ok().map(Value::from),
+        glib::Type::U64 => value.get::<u64>().ok().map(Value::from),
+        glib::Type::F32 => value.get::<f32>().ok().map(|v: f32| json!(v)),
+        glib::Type::F64 => value.get::<f64>().ok().map(Value::from),
+        _ => value
+            .get::<gst::Structure>()
+            .ok()
+            .map(|structure| serialize_structure(&structure)),
+    }
+}
+
+fn serialize_structure(structure: &gst::Structure) -> Value {
+    let mut map = serde_json::Map::new();
+    for (field, value) in structure.iter() {
+        if let Some(json_value) = serialize_value(value) {
+            map.insert(field.to_string(), json_value);
+        }
+    }
+    Value::Object(map)
+}
+
+/// Read whatever properties on `element` happen to carry useful debugging
+/// stats (queue levels, bitrates, ...), skipping ones we can't serialize.
+fn element_stats(element: &gst::Element) -> Value {
+    let mut stats = serde_json::Map::new();
+    for pspec in element.list_properties() {
+        if !pspec.flags().contains(glib::ParamFlags::READABLE) {
+            // Write-only (action-style) properties would panic on read.
+            continue;
+        }
+
+        let name = pspec.name();
+        let value = element.property_value(name);
+        if let Some(json_value) = serialize_value(&value) {
+            stats.insert(name.to_string(), json_value);
+        }
+    }
+    Value::Object(stats)
+}
+
+/// The (src pad -> peer element/pad) links leaving `element`.
+fn element_links(element: &gst::Element) -> Vec<Value> {
+    element
+        .iterate_src_pads()
+        .into_iter()
+        .flatten()
+        .filter_map(|pad| {
+            let peer = pad.peer()?;
+            let peer_element = peer.parent_element()?;
+            Some(json!({
+                "from": element.name(),
+                "from_pad": pad.name(),
+                "to": peer_element.name(),
+                "to_pad": peer.name(),
+            }))
+        })
+        .collect()
+}
+
+fn snapshot(pipeline: &gst::Pipeline) -> (Vec<Value>, Vec<Value>, Value) {
+    let mut elements = Vec::new();
+    let mut links = Vec::new();
+    let mut stats = serde_json::Map::new();
+
+    let iter = pipeline
+        .upcast_ref::<gst::Bin>()
+        .iterate_recurse()
+        .into_iter()
+        .flatten();
+    for element in iter {
+        let name = element.name().to_string();
+        elements.push(json!({
+            "name": name,
+            "factory": element.factory().map(|f| f.name().to_string()),
+            "state": format!("{:?}", element.current_state()),
+        }));
+        links.extend(element_links(&element));
+        stats.insert(name, element_stats(&element));
+    }
+
+    (elements, links, Value::Object(stats))
+}
+
+/// Parse `launch`, set it playing, and poll it on `crate::RUNTIME` until its
+/// current state drops below `Paused`, broadcasting `GraphUpdate` diffs to
+/// every client.
+pub fn start(app: Arc<GstDots>, launch: String) {
+    crate::RUNTIME.spawn(async move {
+        if let Err(err) = gst::init() {
+            event!(Level::ERROR, "Could not initialize GStreamer: {err:?}");
+            return;
+        }
+
+        let pipeline = match gst::parse::launch(&launch) {
+            Ok(element) => match element.downcast::<gst::Pipeline>() {
+                Ok(pipeline) => pipeline,
+                Err(element) => {
+                    let pipeline = gst::Pipeline::new();
+                    pipeline.add(&element).expect("Could not add element to pipeline");
+                    pipeline
+                }
+            },
+            Err(err) => {
+                event!(Level::ERROR, "Could not parse live pipeline {launch:?}: {err:?}");
+                return;
+            }
+        };
+
+        if let Err(err) = pipeline.set_state(gst::State::Playing) {
+            event!(Level::ERROR, "Could not start live pipeline: {err:?}");
+            return;
+        }
+
+        let mut last_elements: HashMap<String, Value> = HashMap::new();
+        let mut last_stats: HashMap<String, Value> = HashMap::new();
+        let mut last_links: Vec<Value> = Vec::new();
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            if pipeline.current_state() < gst::State::Paused {
+                event!(Level::INFO, "Live pipeline stopped, ending live mode");
+                break;
+            }
+
+            let (elements, links, stats) = snapshot(&pipeline);
+            let changed_elements: Vec<Value> = elements
+                .into_iter()
+                .filter(|element| {
+                    let name = element["name"].as_str().unwrap_or_default().to_string();
+                    let changed = last_elements.get(&name) != Some(element);
+                    last_elements.insert(name, element.clone());
+                    changed
+                })
+                .collect();
+
+            let changed_stats: serde_json::Map<String, Value> = match &stats {
+                Value::Object(map) => map
+                    .iter()
+                    .filter(|(name, value)| last_stats.get(*name) != Some(*value))
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect(),
+                _ => serde_json::Map::new(),
+            };
+            if let Value::Object(map) = stats {
+                last_stats.extend(map);
+            }
+
+            let links_changed = links != last_links;
+            if links_changed {
+                last_links = links.clone();
+            }
+
+            if changed_elements.is_empty() && changed_stats.is_empty() && !links_changed {
+                continue;
+            }
+
+            app.broadcast_json(json!({
+                "type": "GraphUpdate",
+                "elements": changed_elements,
+                "links": if links_changed { links } else { Vec::new() },
+                "stats": changed_stats,
+            }));
+        }
+
+        let _ = pipeline.set_state(gst::State::Null);
+    });
+}
@@ -6,20 +6,22 @@ use actix_web::{web, App, Error, HttpRequest, HttpResponse, HttpServer};
 use actix_web_actors::ws;
 use actix_web_static_files::ResourceFiles;
 use clap::{ArgAction, Parser};
-use notify::Watcher;
 use once_cell::sync::Lazy;
+use serde::Deserialize;
 use serde_json::json;
 use std::collections::HashMap;
 use std::fmt;
-use std::path::Path;
-use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::time::SystemTime;
 use tokio::runtime;
-use tracing::error;
 use tracing::instrument;
 use tracing::{event, Level};
 
+mod dot_source;
+mod live;
+mod render;
+
+use dot_source::{DotEvent, DotSource, LocalFsSource, RemoteSource, SocketPushSource};
+
 include!(concat!(env!("OUT_DIR"), "/generated.rs"));
 
 pub static RUNTIME: Lazy<runtime::Runtime> = Lazy::new(|| {
@@ -45,6 +47,23 @@ struct Args {
     /// Server port
     #[arg(short, long, action = ArgAction::Set)]
     dotdir: Option<String>,
+
+    /// Also accept dots pushed over a TCP socket (e.g. "0.0.0.0:3001"),
+    /// instead of relying solely on files written to `dotdir`.
+    #[arg(long, action = ArgAction::Set)]
+    push_socket: Option<String>,
+
+    /// Run a live pipeline (given as a gst-launch description) and stream
+    /// its topology and per-element stats over the websocket, instead of
+    /// only replaying static dot dumps.
+    #[arg(long, action = ArgAction::Set)]
+    live: Option<String>,
+
+    /// Act as a manager: connect to another gst-dots instance's WebSocket
+    /// (e.g. "otherhost:3000") and merge its dots into this one's, namespaced
+    /// by that host. May be repeated to aggregate a whole fleet.
+    #[arg(long, action = ArgAction::Append)]
+    connect: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -52,11 +71,67 @@ struct GstDots {
     gstdot_path: std::path::PathBuf,
     svg_path: std::path::PathBuf,
     html_path: std::path::PathBuf,
-    clients: Arc<Mutex<Vec<Addr<WebSocket>>>>,
-    dot_watcher: Mutex<Option<notify::RecommendedWatcher>>,
+    clients: Arc<Mutex<Vec<Arc<ClientEntry>>>>,
+    sources: Mutex<Vec<Arc<dyn DotSource>>>,
     args: Args,
 }
 
+/// A connected WebSocket client, along with the [`SetFilter`](WsCommand::SetFilter)
+/// glob it has opted into, if any, and the last mtime we've sent it for each
+/// dot (keyed by full relative path) so later events can be deduplicated.
+#[derive(Debug)]
+struct ClientEntry {
+    addr: Addr<WebSocket>,
+    filter: Mutex<Option<glob::Pattern>>,
+    sent: Mutex<HashMap<String, u128>>,
+}
+
+/// JSON commands a client can send over the WebSocket, processed strictly
+/// in arrival order for a given connection.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+enum WsCommand {
+    /// Sent as a client's first message: the dots (and mtimes) it already
+    /// has, so the server only needs to send what changed since.
+    Manifest {
+        known: HashMap<String, u128>,
+    },
+    DeleteDot {
+        id: Option<String>,
+        name: String,
+    },
+    RequestDot {
+        id: Option<String>,
+        name: String,
+    },
+    SetFilter {
+        id: Option<String>,
+        glob: String,
+    },
+    Rename {
+        id: Option<String>,
+        name: String,
+        new_name: String,
+    },
+}
+
+/// Whether a client-supplied dot name is safe to join onto `gstdot_path`:
+/// no absolute paths, no `..`/`.` components to escape it. Pulled out of
+/// [`GstDots::sanitize_dot_path`] so it's testable without a `GstDots`.
+fn is_safe_dot_name(name: &str) -> bool {
+    std::path::Path::new(name)
+        .components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)))
+}
+
+/// Whether `mtime` is a version of a dot the other side hasn't seen yet,
+/// given the mtime it's known to already have (if any). Shared by the
+/// reconnect delta-sync in [`GstDots::sync_dots`] and the live dedup in
+/// [`GstDots::broadcast`].
+fn is_newer(known_mtime: Option<u128>, mtime: u128) -> bool {
+    known_mtime.map(|known| mtime > known).unwrap_or(true)
+}
+
 impl GstDots {
     fn new() -> Arc<Self> {
         let args = Args::parse();
@@ -79,220 +154,410 @@ impl GstDots {
         html_path.push(".generated/html/");
         std::fs::create_dir_all(&html_path).expect("Failed to create svg directory");
 
+        let mut sources: Vec<Arc<dyn DotSource>> = vec![LocalFsSource::new(gstdot_path.clone())];
+        if let Some(address) = args.push_socket.clone() {
+            sources.push(SocketPushSource::new(address));
+        }
+        for host in &args.connect {
+            sources.push(RemoteSource::new(host.clone()));
+        }
+
         let app = Arc::new(Self {
             gstdot_path: gstdot_path.clone(),
             svg_path,
             html_path,
             args,
             clients: Arc::new(Mutex::new(Vec::new())),
-            dot_watcher: Default::default(),
+            sources: Mutex::new(sources),
         });
         app.watch_dot_files();
         app.cleanup_dirs();
 
+        if let Some(launch) = app.args.live.clone() {
+            live::start(app.clone(), launch);
+        }
+
         app
     }
 
-    fn relative_dot_path(&self, dot_path: &Path) -> String {
-        dot_path
-            .strip_prefix(&self.gstdot_path)
-            .unwrap()
-            .to_string_lossy()
-            .to_string()
+    /// Send a pre-built JSON message (e.g. a live `GraphUpdate`) to every
+    /// connected client, bypassing the per-dot filter/dedup bookkeeping
+    /// that only makes sense for `DotEvent`s.
+    fn broadcast_json(&self, value: serde_json::Value) {
+        let message = value.to_string();
+        for entry in self.clients.lock().unwrap().iter() {
+            entry.addr.do_send(TextMessage(message.clone()));
+        }
     }
 
     fn cleanup_dirs(self: &Arc<Self>) {
-        for (dir, ext) in &[(&self.svg_path, "svg"), (&self.html_path, "html")] {
-            let entries = std::fs::read_dir(dir).expect("Could not read svg directory");
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if self.dot_path_for_file(&path).exists() {
-                    event!(Level::DEBUG, "Keeping {ext}: {path:?}");
+        // The html cache is still named after its source dot file.
+        let entries = std::fs::read_dir(&self.html_path).expect("Could not read html directory");
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if self.dot_path_for_file(&path).exists() {
+                event!(Level::DEBUG, "Keeping html: {path:?}");
+                continue;
+            }
+
+            if path.extension().map(|e| e == "html").unwrap_or(false) {
+                if let Err(e) = std::fs::remove_file(&path) {
+                    event!(Level::ERROR, "Failed to remove html: {:?}", e);
+                } else {
+                    event!(
+                        Level::INFO,
+                        "Removed html: {path:?}, {:?} does not exist",
+                        self.dot_path_for_file(&path)
+                    );
+                }
+            }
+        }
+
+        // The svg cache is content-addressed by hash, so it's kept as long
+        // as any currently known dot still hashes to that file.
+        let live_hashes = self.live_content_hashes();
+        let entries = std::fs::read_dir(&self.svg_path).expect("Could not read svg directory");
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|e| e == "svg").unwrap_or(false) {
+                let hash = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+                if live_hashes.contains(hash) {
+                    event!(Level::DEBUG, "Keeping svg: {path:?}");
                     continue;
                 }
 
-                if path.extension().map(|e| &e == ext).unwrap_or(false) {
-                    if let Err(e) = std::fs::remove_file(&path) {
-                        event!(Level::ERROR, "Failed to remove {ext}: {:?}", e);
-                    } else {
-                        event!(
-                            Level::INFO,
-                            "Removed {ext}: {path:?}, {:?} does not exist",
-                            self.dot_path_for_file(&path)
-                        );
-                    }
+                if let Err(e) = std::fs::remove_file(&path) {
+                    event!(Level::ERROR, "Failed to remove svg: {:?}", e);
+                } else {
+                    event!(Level::INFO, "Removed svg: {path:?}, no longer referenced by any dot");
                 }
             }
         }
     }
 
+    fn live_content_hashes(&self) -> std::collections::HashSet<String> {
+        self.sources
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|source| source.list())
+            .map(|(_, content, _)| render::content_hash(&content))
+            .collect()
+    }
+
     fn dot_path_for_file(&self, path: &std::path::Path) -> std::path::PathBuf {
         let file_name = path.file_name().unwrap();
 
         self.gstdot_path.join(file_name).with_extension("dot")
     }
 
-    fn modify_time(&self, path: &std::path::Path) -> u128 {
-        self.dot_path_for_file(path)
-            .metadata()
-            .map(|m| m.modified().unwrap_or(std::time::UNIX_EPOCH))
-            .unwrap_or(std::time::UNIX_EPOCH)
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis()
-    }
-
-    fn collect_dot_files(path: &PathBuf, entries: &mut Vec<(PathBuf, SystemTime)>) {
-        if let Ok(read_dir) = std::fs::read_dir(path) {
-            for entry in read_dir.flatten() {
-                let dot_path = entry.path();
-                if dot_path.is_dir() {
-                    // Recursively call this function if the path is a directory
-                    Self::collect_dot_files(&dot_path, entries);
-                } else {
-                    // Process only `.dot` files
-                    if dot_path.extension().and_then(|e| e.to_str()) == Some("dot") {
-                        if let Ok(metadata) = dot_path.metadata() {
-                            if let Ok(modified) = metadata.modified() {
-                                entries.push((dot_path, modified));
-                            }
-                        }
-                    }
-                }
-            }
+    /// Queue `content` for server-side rendering (if not cached already) and
+    /// return the URL clients can fetch the resulting svg from. This runs
+    /// once per dot per client on every listing/sync/broadcast, so the cache
+    /// is checked synchronously here - a cache hit shouldn't cost a
+    /// `RUNTIME` task spawn, only a `dot` process is worth bounding.
+    fn dot_svg_url(&self, content: &str) -> String {
+        let hash = render::content_hash(content);
+        let dest = self.svg_path.join(&hash).with_extension("svg");
+        if !dest.exists() {
+            render::render_to_cache(content.to_string(), self.svg_path.clone(), hash.clone());
         }
+        format!("/svg/{hash}.svg")
     }
 
-    fn list_dots(&self, client: Addr<WebSocket>) {
-        event!(Level::DEBUG, "Listing dot files in {:?}", self.gstdot_path);
-        let mut entries: Vec<(PathBuf, SystemTime)> = Vec::new();
+    fn new_dot_message(&self, name: &str, content: &str, mtime: u128) -> serde_json::Value {
+        json!({
+            "type": "NewDot",
+            "name": name,
+            "content": content,
+            "creation_time": mtime,
+            "svg_url": self.dot_svg_url(content),
+        })
+    }
 
-        let start_path = PathBuf::from(&self.gstdot_path);
-        Self::collect_dot_files(&start_path, &mut entries);
+    /// Send every dot currently known by any registered [`DotSource`] to `client`,
+    /// honoring its `SetFilter` glob, if any. Used to resend the current list
+    /// after a filter change; reconnects go through [`Self::sync_dots`] instead.
+    fn list_dots(&self, client: Addr<WebSocket>) {
+        event!(Level::DEBUG, "Listing dots from {} source(s)", self.sources.lock().unwrap().len());
+        let filter = self.client_filter(&client);
 
-        entries.sort_by(|a, b| a.1.cmp(&b.1));
+        let mut dots: Vec<(String, String, u128)> = self
+            .sources
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|source| source.list())
+            .collect();
+        dots.sort_by(|a, b| a.2.cmp(&b.2));
 
-        for (dot_path, _) in entries {
-            let content = match std::fs::read_to_string(&dot_path) {
-                Ok(c) => c,
-                Err(_) => {
-                    event!(Level::ERROR, "===>Error reading file: {:?}", dot_path);
-                    continue;
-                }
-            };
-            if content.is_empty() {
-                event!(Level::ERROR, "===>Empty file: {:?}", dot_path);
+        for (name, content, mtime) in dots {
+            if filter.as_ref().map(|f| !f.matches(&name)).unwrap_or(false) {
                 continue;
             }
-
-            let name = self.relative_dot_path(&dot_path);
             event!(Level::INFO, "Sending `{name}` to client: {client:?}");
             client.do_send(TextMessage(
-                json!({
-                    "type": "NewDot",
-                    "name": name,
-                    "content": content,
-                    "creation_time": self.modify_time(&dot_path),
-                })
-                .to_string(),
+                self.new_dot_message(&name, &content, mtime).to_string(),
             ));
+            self.mark_sent(&client, &name, mtime);
         }
     }
 
-    fn watch_dot_files(self: &Arc<Self>) {
-        let app_clone = self.clone();
-        let mut dot_watcher =
-            notify::recommended_watcher(move |event: Result<notify::Event, notify::Error>| {
-                match event {
-                    Ok(event) => {
-                        if event
-                            .paths
-                            .iter()
-                            .any(|p| p.extension().map(|e| e == "dot").unwrap_or(false))
-                        {
-                            match event.kind {
-                                notify::event::EventKind::Modify(notify::event::ModifyKind::Data(notify::event::DataChange::Content)) => {
-                                    for path in event.paths.iter() {
-                                        event!(Level::INFO, "File created: {:?}", path);
-                                        if path.extension().map(|e| e == "dot").unwrap_or(false) {
-                                            let path = path.to_path_buf();
-                                            let clients = app_clone.clients.lock().unwrap();
-                                            let clients = clients.clone();
-
-                                            for client in clients.iter() {
-                                                let name = app_clone.relative_dot_path(&path);
-                                                event!(Level::DEBUG, "Sending {name} to client: {client:?}");
-                                                match std::fs::read_to_string(&path) {
-                                                    Ok(content) => client.do_send(TextMessage(
-                                                        json!({
-                                                            "type": "NewDot",
-                                                            "name": name,
-                                                            "content": content,
-                                                            "creation_time": app_clone.modify_time(&event.paths[0]),
-                                                        })
-                                                        .to_string(),
-                                                    )),
-                                                    Err(err) => error!("Could not read file {path:?}: {err:?}"),
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                                notify::event::EventKind::Remove(_) => {
-                                    event!(Level::INFO, "File removed: {:?}", event.paths);
-                                    for path in event.paths.iter() {
-                                        event!(Level::INFO, "File created: {:?}", path);
-                                        if path.extension().map(|e| e == "dot").unwrap_or(false) {
-                                            let path = path.to_path_buf();
-                                            let clients = app_clone.clients.lock().unwrap();
-                                            let clients = clients.clone();
-
-                                            for client in clients.iter() {
-                                                event!(Level::INFO, "Sending to client: {:?}", client);
-                                                client.do_send(TextMessage(
-                                                    json!({
-                                                        "type": "DotRemoved",
-                                                        "name": path.file_name().unwrap().to_str().unwrap(),
-                                                        "creation_time": app_clone.modify_time(&event.paths[0]),
-                                                    })
-                                                    .to_string(),
-                                                ));
-                                            }
-                                        }
-                                    }
-                                }
-                                _ => (),
-                            }
-                        }
+    /// Handshake for a reconnecting client: given the `(name, mtime)` pairs it
+    /// already has, send `NewDot` only for dots that are missing or newer,
+    /// and `DotRemoved` for anything it has that no longer exists.
+    fn sync_dots(&self, client: Addr<WebSocket>, known: HashMap<String, u128>) {
+        let filter = self.client_filter(&client);
+
+        let mut dots: Vec<(String, String, u128)> = self
+            .sources
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|source| source.list())
+            .collect();
+        dots.sort_by(|a, b| a.2.cmp(&b.2));
+
+        let mut current = std::collections::HashSet::with_capacity(dots.len());
+        for (name, content, mtime) in dots {
+            current.insert(name.clone());
+            if filter.as_ref().map(|f| !f.matches(&name)).unwrap_or(false) {
+                continue;
+            }
+
+            if is_newer(known.get(&name).copied(), mtime) {
+                event!(Level::INFO, "Sending `{name}` to reconnected client: {client:?}");
+                client.do_send(TextMessage(
+                    self.new_dot_message(&name, &content, mtime).to_string(),
+                ));
+            }
+            self.mark_sent(&client, &name, mtime);
+        }
+
+        for name in known.keys() {
+            if !current.contains(name) {
+                client.do_send(TextMessage(
+                    json!({ "type": "DotRemoved", "name": name }).to_string(),
+                ));
+            }
+        }
+    }
+
+    /// Fan a [`DotEvent`] from any source out to every connected client whose
+    /// filter (if set) matches the dot's name, skipping clients we've
+    /// already sent this exact (or a newer) version to.
+    fn broadcast(&self, event: DotEvent) {
+        let clients = self.clients.lock().unwrap().clone();
+        let name = match &event {
+            DotEvent::Added { name, .. } => name,
+            DotEvent::Removed { name } => name,
+        };
+
+        for entry in clients.iter() {
+            if let Some(filter) = entry.filter.lock().unwrap().as_ref() {
+                if !filter.matches(name) {
+                    continue;
+                }
+            }
+
+            match &event {
+                DotEvent::Added { name, content, mtime } => {
+                    let already_sent =
+                        !is_newer(entry.sent.lock().unwrap().get(name).copied(), *mtime);
+                    if already_sent {
+                        continue;
                     }
-                    Err(err) => event!(Level::ERROR, "watch error: {:?}", err),
+
+                    event!(Level::DEBUG, "Sending {event:?} to client: {:?}", entry.addr);
+                    entry.addr.do_send(TextMessage(
+                        self.new_dot_message(name, content, *mtime).to_string(),
+                    ));
+                    entry.sent.lock().unwrap().insert(name.clone(), *mtime);
+                }
+                DotEvent::Removed { name } => {
+                    entry.sent.lock().unwrap().remove(name);
+                    event!(Level::DEBUG, "Sending {event:?} to client: {:?}", entry.addr);
+                    entry
+                        .addr
+                        .do_send(TextMessage(json!({ "type": "DotRemoved", "name": name }).to_string()));
                 }
-            })
-            .expect("Could not create dot_watcher");
+            }
+        }
+    }
+
+    fn watch_dot_files(self: &Arc<Self>) {
+        let sources = self.sources.lock().unwrap().clone();
+        for source in sources {
+            let app_clone = self.clone();
+            source.subscribe(Arc::new(move |event| app_clone.broadcast(event)));
+        }
+    }
+
+    fn client_filter(&self, addr: &Addr<WebSocket>) -> Option<glob::Pattern> {
+        self.clients
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|entry| &entry.addr == addr)
+            .and_then(|entry| entry.filter.lock().unwrap().clone())
+    }
+
+    fn mark_sent(&self, addr: &Addr<WebSocket>, name: &str, mtime: u128) {
+        if let Some(entry) = self.clients.lock().unwrap().iter().find(|entry| &entry.addr == addr) {
+            entry.sent.lock().unwrap().insert(name.to_string(), mtime);
+        }
+    }
+
+    fn set_filter(&self, addr: &Addr<WebSocket>, filter: glob::Pattern) {
+        if let Some(entry) = self.clients.lock().unwrap().iter().find(|entry| &entry.addr == addr) {
+            *entry.filter.lock().unwrap() = Some(filter);
+        }
+    }
+
+    /// Resolve a client-supplied dot name to a path inside `gstdot_path`,
+    /// rejecting anything that could escape it (absolute paths, `..`). Names
+    /// come straight off the WebSocket, so this must run before any of them
+    /// reach `remove_file`/`rename`.
+    fn sanitize_dot_path(&self, name: &str) -> Result<std::path::PathBuf, String> {
+        if !is_safe_dot_name(name) {
+            return Err(format!("Invalid dot name: {name}"));
+        }
+        Ok(self.gstdot_path.join(name))
+    }
+
+    /// Remove a dot's source file plus its cached svg/html renders.
+    fn delete_dot(&self, name: &str) -> Result<(), String> {
+        let path = self.sanitize_dot_path(name)?;
+        let content = std::fs::read_to_string(&path).ok();
+        std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+
+        // The svg cache is content-addressed, so only drop it if no other
+        // known dot still hashes to the same file.
+        if let Some(content) = content {
+            let hash = render::content_hash(&content);
+            if !self.live_content_hashes().contains(&hash) {
+                let _ = std::fs::remove_file(self.svg_path.join(&hash).with_extension("svg"));
+            }
+        }
+
+        if let Some(file_name) = path.file_name() {
+            let _ = std::fs::remove_file(self.html_path.join(file_name).with_extension("html"));
+        }
+
+        Ok(())
+    }
+
+    /// Rename a dot's source file and tell clients it moved. A plain rename
+    /// isn't reported by the filesystem watcher as `Content`/`Remove`, so
+    /// unlike [`Self::delete_dot`] this can't rely on the watcher's own
+    /// broadcast - it has to raise `Removed`/`Added` itself.
+    fn rename_dot(&self, name: &str, new_name: &str) -> Result<(), String> {
+        let src = self.sanitize_dot_path(name)?;
+        let dest = self.sanitize_dot_path(new_name)?;
+
+        // `fs::rename` silently clobbers an existing `dest` (POSIX semantics),
+        // which would leave every client showing the destroyed dot's stale
+        // content forever - the watcher can't catch this either, since a
+        // rename-over-existing-file is also just `ModifyKind::Name`.
+        if dest.exists() {
+            return Err(format!("A dot named {new_name} already exists"));
+        }
+
+        std::fs::rename(&src, &dest).map_err(|e| e.to_string())?;
+
+        self.broadcast(DotEvent::Removed { name: name.to_string() });
 
-        event!(Level::INFO, "Watching dot files in {:?}", self.gstdot_path);
-        dot_watcher
-            .watch(self.gstdot_path.as_path(), notify::RecursiveMode::Recursive)
-            .unwrap();
-        *self.dot_watcher.lock().unwrap() = Some(dot_watcher);
+        let mtime = dest
+            .metadata()
+            .and_then(|m| m.modified())
+            .map(|t| t.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis())
+            .unwrap_or_default();
+        match std::fs::read_to_string(&dest) {
+            Ok(content) => self.broadcast(DotEvent::Added {
+                name: new_name.to_string(),
+                content,
+                mtime,
+            }),
+            Err(err) => event!(Level::ERROR, "Could not read renamed dot {dest:?}: {err:?}"),
+        }
+
+        Ok(())
+    }
+
+    fn ack(&self, client: &Addr<WebSocket>, id: Option<String>) {
+        client.do_send(TextMessage(json!({ "type": "Ack", "id": id }).to_string()));
+    }
+
+    fn command_error(&self, client: &Addr<WebSocket>, id: Option<String>, message: impl Into<String>) {
+        client.do_send(TextMessage(
+            json!({ "type": "Error", "id": id, "message": message.into() }).to_string(),
+        ));
     }
 
+    fn handle_command(self: &Arc<Self>, client: Addr<WebSocket>, cmd: WsCommand) {
+        match cmd {
+            WsCommand::Manifest { known } => self.sync_dots(client, known),
+            // The filesystem watcher picks up the resulting `remove_file` and
+            // broadcasts `Removed` to every client on its own (see
+            // `LocalFsSource::subscribe`), so this doesn't broadcast again.
+            WsCommand::DeleteDot { id, name } => match self.delete_dot(&name) {
+                Ok(()) => self.ack(&client, id),
+                Err(err) => self.command_error(&client, id, err),
+            },
+            WsCommand::RequestDot { id, name } => {
+                let found = self
+                    .sources
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .flat_map(|source| source.list())
+                    .find(|(n, _, _)| *n == name);
+                match found {
+                    Some((name, content, mtime)) => {
+                        client.do_send(TextMessage(
+                            self.new_dot_message(&name, &content, mtime).to_string(),
+                        ));
+                        self.mark_sent(&client, &name, mtime);
+                        self.ack(&client, id);
+                    }
+                    None => self.command_error(&client, id, format!("No such dot: {name}")),
+                }
+            }
+            WsCommand::SetFilter { id, glob: pattern } => match glob::Pattern::new(&pattern) {
+                Ok(pattern) => {
+                    self.set_filter(&client, pattern);
+                    self.list_dots(client.clone());
+                    self.ack(&client, id);
+                }
+                Err(err) => self.command_error(&client, id, err.to_string()),
+            },
+            WsCommand::Rename { id, name, new_name } => match self.rename_dot(&name, &new_name) {
+                Ok(()) => self.ack(&client, id),
+                Err(err) => self.command_error(&client, id, err),
+            },
+        }
+    }
+
+    /// Register a newly connected client. It isn't sent the current dots until
+    /// it sends its `Manifest` handshake (see [`Self::sync_dots`]); events
+    /// broadcast after registration reach it before the handshake too.
     #[instrument(level = "trace")]
     fn add_client(&self, client: Addr<WebSocket>) {
         let mut clients = self.clients.lock().unwrap();
 
         event!(Level::INFO, "Client added: {:?}", client);
-        clients.push(client.clone());
-        drop(clients);
-
-        self.list_dots(client);
+        clients.push(Arc::new(ClientEntry {
+            addr: client,
+            filter: Mutex::new(None),
+            sent: Mutex::new(HashMap::new()),
+        }));
     }
 
     #[instrument(level = "trace")]
     fn remove_client(&self, addr: &Addr<WebSocket>) {
         event!(Level::INFO, "Client removed: {:?}", addr);
         let mut clients = self.clients.lock().unwrap();
-        clients.retain(|a| a != addr);
+        clients.retain(|entry| &entry.addr != addr);
     }
 
     async fn run(self: &Arc<Self>) -> std::io::Result<()> {
@@ -349,9 +614,19 @@ impl Handler<TextMessage> for WebSocket {
 }
 
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WebSocket {
-    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, _ctx: &mut Self::Context) {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
         if let Ok(ws::Message::Text(text)) = msg {
             event!(Level::INFO, "Message received: {:?}", text);
+            match serde_json::from_str::<WsCommand>(&text) {
+                Ok(cmd) => self.app.handle_command(ctx.address(), cmd),
+                Err(err) => {
+                    event!(Level::ERROR, "Invalid command {:?}: {err:?}", text);
+                    ctx.text(
+                        json!({ "type": "Error", "id": null, "message": err.to_string() })
+                            .to_string(),
+                    );
+                }
+            }
         }
     }
 }
@@ -396,3 +671,41 @@ async fn main() -> std::io::Result<()> {
     }
     GstDots::new().run().await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_plain_names() {
+        assert!(is_safe_dot_name("pipeline.dot"));
+        assert!(is_safe_dot_name("subdir/pipeline.dot"));
+    }
+
+    #[test]
+    fn rejects_parent_dir_components() {
+        assert!(!is_safe_dot_name("../../etc/passwd"));
+        assert!(!is_safe_dot_name("a/../../b.dot"));
+    }
+
+    #[test]
+    fn rejects_absolute_paths() {
+        assert!(!is_safe_dot_name("/etc/passwd"));
+    }
+
+    #[test]
+    fn unknown_dot_is_newer() {
+        assert!(is_newer(None, 42));
+    }
+
+    #[test]
+    fn strictly_newer_mtime_is_newer() {
+        assert!(is_newer(Some(10), 20));
+    }
+
+    #[test]
+    fn equal_or_older_mtime_is_not_newer() {
+        assert!(!is_newer(Some(20), 20));
+        assert!(!is_newer(Some(20), 10));
+    }
+}
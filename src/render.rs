@@ -0,0 +1,71 @@
+//! Server-side `.dot` -> `.svg` rendering into the `svg_path` cache.
+//!
+//! Rendering used to be left entirely to the browser. For large pipelines
+//! that's slow and gets redone by every connected client, so instead we
+//! render once on the server and hand clients a URL into the cache. A
+//! semaphore-guarded pool bounds how many `dot` processes can run at once,
+//! since `RUNTIME` only has a single worker thread and a burst of dumps
+//! shouldn't be able to spawn one process per dot.
+
+use std::path::PathBuf;
+
+use once_cell::sync::Lazy;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+use tracing::{event, Level};
+
+const MAX_CONCURRENT_RENDERS: usize = 4;
+
+static RENDER_PERMITS: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(MAX_CONCURRENT_RENDERS));
+
+/// Content-address a dot's content so identical dumps share one cached svg.
+pub fn content_hash(content: &str) -> String {
+    blake3::hash(content.as_bytes()).to_hex().to_string()
+}
+
+/// Render `content` to `<svg_path>/<hash>.svg` if it isn't cached already.
+/// Spawned onto `crate::RUNTIME`; does not block the caller.
+pub fn render_to_cache(content: String, svg_path: PathBuf, hash: String) {
+    crate::RUNTIME.spawn(async move {
+        let dest = svg_path.join(&hash).with_extension("svg");
+        if dest.exists() {
+            return;
+        }
+
+        let _permit = RENDER_PERMITS.acquire().await.expect("semaphore closed");
+        // Another renderer may have raced us while we waited for a permit.
+        if dest.exists() {
+            return;
+        }
+
+        let mut child = match tokio::process::Command::new("dot")
+            .arg("-Tsvg")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                event!(Level::ERROR, "Could not spawn graphviz: {err:?}");
+                return;
+            }
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            if let Err(err) = stdin.write_all(content.as_bytes()).await {
+                event!(Level::ERROR, "Could not write dot content to graphviz: {err:?}");
+                return;
+            }
+        }
+
+        match child.wait_with_output().await {
+            Ok(output) if output.status.success() => {
+                if let Err(err) = tokio::fs::write(&dest, &output.stdout).await {
+                    event!(Level::ERROR, "Could not write svg cache {dest:?}: {err:?}");
+                }
+            }
+            Ok(output) => event!(Level::ERROR, "graphviz exited with {:?}", output.status),
+            Err(err) => event!(Level::ERROR, "Could not wait for graphviz: {err:?}"),
+        }
+    });
+}